@@ -1,5 +1,4 @@
 use std::{
-    fs::ReadDir,
     io,
     path::{Path, PathBuf},
     str::FromStr,
@@ -37,9 +36,11 @@ pub enum ConfigError {
     FieldMissing,
     #[error("failed to call from_str for the desired type")]
     FromStrErr,
+    #[error("could not determine a file format from the path's extension")]
+    UnknownFormat,
 }
 
-pub struct StorageHolder<CS: ConfigStorage>(CS);
+pub struct StorageHolder<CS: ConfigStorage>(CS, bool);
 
 pub struct SdCardStorage(std::path::PathBuf);
 
@@ -73,7 +74,10 @@ impl ConfigStorage for SdCardStorage {
 /// Abstraction over the configuration directory created for your plugin for the current user.
 ///
 /// It is heavily recommended to **NOT** manipulate \"config:/\" yourself, and instead use the methods implemented on ConfigStorage for safety reasons.
-pub struct DebugSavedataStorage(std::path::PathBuf);
+pub struct DebugSavedataStorage {
+    uid: nn::account::Uid,
+    plugin_name: PathBuf,
+}
 
 impl DebugSavedataStorage {
     pub fn new<P: AsRef<Path>>(plugin_name: P) -> Self {
@@ -97,13 +101,18 @@ impl DebugSavedataStorage {
             drop(handle);
         }
 
-        // Generate path for the current user so each user can have their own configuration
-        let path = PathBuf::from(uid.id[0].to_string()).join(uid.id[1].to_string()).join(plugin_name);
-
-        Self(path)
+        Self {
+            uid,
+            plugin_name: plugin_name.as_ref().to_path_buf(),
+        }
     }
 }
 
+/// Generates the `uid.id[0]/uid.id[1]` path prefix each user's configuration tree lives under.
+fn uid_path(uid: &nn::account::Uid) -> PathBuf {
+    PathBuf::from(uid.id[0].to_string()).join(uid.id[1].to_string())
+}
+
 impl ConfigStorage for DebugSavedataStorage {
     fn initialize(&self) -> Result<(), ConfigError> {
         unsafe {
@@ -125,7 +134,7 @@ impl ConfigStorage for DebugSavedataStorage {
     }
 
     fn storage_path(&self) -> PathBuf {
-        self.root_path().join(&self.0)
+        self.root_path().join(uid_path(&self.uid)).join(&self.plugin_name)
     }
 
     fn require_flushing(&self) -> bool {
@@ -146,64 +155,167 @@ impl Drop for DebugSavedataStorage {
     }
 }
 
-impl<CS: ConfigStorage> StorageHolder<CS> {
-    // /// TODO: Rework this to allow copying the config from one user to the other using the UID to compute paths.
-    // fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> io::Result<u64> {
-    //     todo!();
+impl StorageHolder<DebugSavedataStorage> {
+    /// Lists every user that has a configuration directory under ``config:/``.
+    pub fn list_users() -> io::Result<Vec<nn::account::Uid>> {
+        unsafe {
+            // Don't check result, we do not care if it is already mounted
+            MountSaveDataForDebug(skyline::c_str("config\0"));
+        }
+
+        let mut users = Vec::new();
+
+        for high_entry in std::fs::read_dir("config:/")? {
+            let high_entry = high_entry?;
+
+            if !high_entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false) {
+                continue;
+            }
+
+            let Ok(high) = high_entry.file_name().to_string_lossy().parse::<u64>() else {
+                continue;
+            };
+
+            let Ok(low_entries) = std::fs::read_dir(high_entry.path()) else {
+                continue;
+            };
+
+            for low_entry in low_entries {
+                let Ok(low_entry) = low_entry else {
+                    continue;
+                };
+
+                if !low_entry.file_type().map(|file_type| file_type.is_dir()).unwrap_or(false) {
+                    continue;
+                }
+
+                let Ok(low) = low_entry.file_name().to_string_lossy().parse::<u64>() else {
+                    continue;
+                };
+
+                users.push(nn::account::Uid { id: [high, low] });
+            }
+        }
+
+        Ok(users)
+    }
+
+    /// Replaces ``dst``'s configuration tree for this plugin with a copy of ``src``'s, then flushes once.
+    pub fn copy_config_from_user(&mut self, src: nn::account::Uid, dst: nn::account::Uid) -> io::Result<()> {
+        if src.id == dst.id {
+            return Ok(());
+        }
+
+        let root = self.0.root_path();
+        let src_path = root.join(uid_path(&src)).join(&self.0.plugin_name);
+        let dst_path = root.join(uid_path(&dst)).join(&self.0.plugin_name);
+
+        if dst_path.exists() {
+            std::fs::remove_dir_all(&dst_path)?;
+        }
+
+        copy_dir_recursive(&src_path, &dst_path)?;
+
+        self.flush();
+        Ok(())
+    }
+}
+
+/// Recursively copies every file and subdirectory from ``from`` into ``to``, creating directories as needed.
+fn copy_dir_recursive(from: &Path, to: &Path) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
 
-    //     let full_path_from = self.0.join(from);
-    //     let full_path_to = self.0.join(to);
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let dst_path = to.join(entry.file_name());
 
-    //     std::fs::copy(full_path_from, full_path_to).map(|res| {
-    //         self.flush();
-    //         res
-    //     })
-    // }
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &dst_path)?;
+        } else {
+            std::fs::copy(entry.path(), &dst_path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Restores a [``StorageHolder``]'s transaction flag and flushes once the transaction ends, whether
+/// [``StorageHolder::with_transaction``]'s closure returned normally or unwound via a panic.
+struct TransactionGuard<'a, CS: ConfigStorage> {
+    holder: &'a mut StorageHolder<CS>,
+    was_in_transaction: bool,
+}
 
+impl<'a, CS: ConfigStorage> std::ops::Deref for TransactionGuard<'a, CS> {
+    type Target = StorageHolder<CS>;
+
+    fn deref(&self) -> &Self::Target {
+        self.holder
+    }
+}
+
+impl<'a, CS: ConfigStorage> std::ops::DerefMut for TransactionGuard<'a, CS> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.holder
+    }
+}
+
+impl<'a, CS: ConfigStorage> Drop for TransactionGuard<'a, CS> {
+    fn drop(&mut self) {
+        self.holder.1 = self.was_in_transaction;
+        self.holder.flush();
+    }
+}
+
+impl<CS: ConfigStorage> StorageHolder<CS> {
     pub fn new(storage: CS) -> Self {
         storage.initialize().unwrap();
-        Self(storage)
+        Self(storage, false)
     }
 
-    fn create<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
-        let full_path = self.0.storage_path().join(path);
+    /// Runs ``body`` inside a write transaction: [``flush``](StorageHolder::flush) becomes a no-op for
+    /// its duration, and a single [``ConfigStorage::perform_flush``] is issued once ``body`` returns,
+    /// instead of one per field written. Transactions can be nested; only the outermost one flushes.
+    ///
+    /// The transaction flag is restored by a guard, so a panic inside ``body`` still ends the
+    /// transaction instead of leaving every later `flush()` on this holder a permanent no-op.
+    pub fn with_transaction<R>(&mut self, body: impl FnOnce(&mut Self) -> R) -> R {
+        let was_in_transaction = self.1;
+        self.1 = true;
 
-        std::fs::File::create(full_path).map(|_| ())
+        let mut guard = TransactionGuard { holder: self, was_in_transaction };
+        body(&mut *guard)
     }
 
-    fn remove_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
-        let full_path = self.0.storage_path().join(path);
+    fn create<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.0.write(path.as_ref(), &[])
+    }
 
-        std::fs::remove_file(full_path)?;
+    fn remove_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<()> {
+        self.0.remove(path.as_ref())?;
         self.flush();
         Ok(())
     }
 
     /// Renames a field or a flag to another name.
     pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, from: P, to: Q) -> Result<(), ConfigError> {
-        let full_path_from = self.0.storage_path().join(from);
-        let full_path_to = self.0.storage_path().join(to);
-
-        std::fs::rename(full_path_from, full_path_to)?;
+        self.0.rename(from.as_ref(), to.as_ref())?;
         self.flush();
         Ok(())
     }
 
-    /// Abstraction of ``std::fs::read_dir`` over the Configuration Storage.
-    pub fn read_dir(&self) -> io::Result<ReadDir> {
-        std::fs::read_dir(&self.0.storage_path())
+    /// Lists every field and flag currently present in the Configuration Storage.
+    pub fn read_dir(&self) -> io::Result<Vec<PathBuf>> {
+        self.0.list()
     }
 
     fn read_to_string<P: AsRef<Path>>(&self, path: P) -> io::Result<String> {
-        let full_path = self.0.storage_path().join(path);
-
-        std::fs::read_to_string(full_path)
+        let contents = self.0.read(path.as_ref())?;
+        String::from_utf8(contents).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
     }
 
     fn write<P: AsRef<Path>, C: AsRef<[u8]>>(&mut self, path: P, contents: C) -> Result<(), ConfigError> {
-        let full_path = self.0.storage_path().join(path);
-
-        std::fs::write(full_path, contents)?;
+        self.0.write(path.as_ref(), contents.as_ref())?;
         self.flush();
         Ok(())
     }
@@ -227,8 +339,7 @@ impl<CS: ConfigStorage> StorageHolder<CS> {
 
     /// Checks if a flag is enabled in the configuration
     pub fn get_flag<P: AsRef<Path>>(&self, path: P) -> bool {
-        let full_path = self.0.storage_path().join(path);
-        std::path::Path::exists(&full_path)
+        self.0.read(path.as_ref()).is_ok()
     }
 
     /// If ``flag`` is set to true, enable the flag if it isn't already set.
@@ -244,20 +355,26 @@ impl<CS: ConfigStorage> StorageHolder<CS> {
     /// Delete every file in the configuration storage.
     /// Be absolutely sure this is what you desire before calling it.
     pub fn clear_storage(&mut self) {
-        self.read_dir().unwrap().for_each(|entry| {
-            std::fs::remove_file(entry.unwrap().path()).unwrap();
-        });
+        for path in self.0.list().unwrap() {
+            self.0.remove(&path).unwrap();
+        }
 
         self.flush();
     }
 
+    /// Delete the entire configuration storage, directory included.
+    /// Be absolutely sure this is what you desire before calling it.
     pub fn delete_storage(&mut self) {
-        std::fs::remove_dir_all(self.0.storage_path()).unwrap();
+        self.0.remove_all().unwrap();
 
         self.flush();
     }
 
     pub fn flush(&self) {
+        if self.1 {
+            return;
+        }
+
         if self.0.require_flushing() {
             self.0.perform_flush();
         }
@@ -271,6 +388,36 @@ pub trait ConfigStorage {
 
     fn storage_path(&self) -> PathBuf;
 
+    /// Reads the raw contents of ``rel``, relative to [``storage_path``](ConfigStorage::storage_path).
+    fn read(&self, rel: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.storage_path().join(rel))
+    }
+
+    /// Writes ``data`` to ``rel``, relative to [``storage_path``](ConfigStorage::storage_path), creating it if it doesn't exist.
+    fn write(&self, rel: &Path, data: &[u8]) -> io::Result<()> {
+        std::fs::write(self.storage_path().join(rel), data)
+    }
+
+    /// Removes ``rel``, relative to [``storage_path``](ConfigStorage::storage_path).
+    fn remove(&self, rel: &Path) -> io::Result<()> {
+        std::fs::remove_file(self.storage_path().join(rel))
+    }
+
+    /// Lists every entry present in the storage, relative to [``storage_path``](ConfigStorage::storage_path).
+    fn list(&self) -> io::Result<Vec<PathBuf>> {
+        std::fs::read_dir(self.storage_path())?.map(|entry| entry.map(|entry| PathBuf::from(entry.file_name()))).collect()
+    }
+
+    /// Renames ``from`` to ``to``, both relative to [``storage_path``](ConfigStorage::storage_path), atomically.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(self.storage_path().join(from), self.storage_path().join(to))
+    }
+
+    /// Removes the entire storage, directory included.
+    fn remove_all(&self) -> io::Result<()> {
+        std::fs::remove_dir_all(self.storage_path())
+    }
+
     fn require_flushing(&self) -> bool {
         false
     }
@@ -278,19 +425,289 @@ pub trait ConfigStorage {
     fn perform_flush(&self) {}
 }
 
+/// In-memory [``ConfigStorage``] backend, mainly useful to unit-test config schemas off-device.
+///
+/// Unlike [``SdCardStorage``] and [``DebugSavedataStorage``], nothing is ever written to disk:
+/// every field and flag simply lives in a [``HashMap``](std::collections::HashMap) for the lifetime of the instance.
+#[derive(Default)]
+pub struct MemoryStorage(std::cell::RefCell<std::collections::HashMap<PathBuf, Vec<u8>>>);
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ConfigStorage for MemoryStorage {
+    fn initialize(&self) -> Result<(), ConfigError> {
+        Ok(())
+    }
+
+    fn root_path(&self) -> PathBuf {
+        PathBuf::from("memory:/")
+    }
+
+    fn storage_path(&self) -> PathBuf {
+        self.root_path()
+    }
+
+    fn read(&self, rel: &Path) -> io::Result<Vec<u8>> {
+        self.0
+            .borrow()
+            .get(rel)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "field not found in memory storage"))
+    }
+
+    fn write(&self, rel: &Path, data: &[u8]) -> io::Result<()> {
+        self.0.borrow_mut().insert(rel.to_path_buf(), data.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, rel: &Path) -> io::Result<()> {
+        self.0
+            .borrow_mut()
+            .remove(rel)
+            .map(|_| ())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "field not found in memory storage"))
+    }
+
+    fn list(&self) -> io::Result<Vec<PathBuf>> {
+        Ok(self.0.borrow().keys().cloned().collect())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut fields = self.0.borrow_mut();
+        let data = fields.remove(from).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "field not found in memory storage"))?;
+        fields.insert(to.to_path_buf(), data);
+        Ok(())
+    }
+
+    fn remove_all(&self) -> io::Result<()> {
+        self.0.borrow_mut().clear();
+        Ok(())
+    }
+}
+
+/// Identifies which layer of a [``LayeredStorage``] produced a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    SdCard,
+    SaveData,
+}
+
+/// Resolves fields and flags across several [``ConfigStorage``] backends at once, in priority order.
+///
+/// This lets a plugin offer a user-editable SD-card override sitting on top of a canonical save-data
+/// configuration: reads return the value from the highest-priority layer that has it, while writes
+/// always go to a single designated writable layer.
+pub struct LayeredStorage {
+    layers: Vec<(Source, Box<dyn ConfigStorage>)>,
+    writable: usize,
+}
+
+impl LayeredStorage {
+    /// Builds a layered storage from ``layers``, ordered from highest to lowest priority.
+    /// ``writable`` is the index of the layer that `set_field`/`set_flag` write to.
+    pub fn new(layers: Vec<(Source, Box<dyn ConfigStorage>)>, writable: usize) -> Self {
+        for (_, layer) in &layers {
+            layer.initialize().unwrap();
+        }
+
+        Self { layers, writable }
+    }
+
+    /// Provides the value of the field from the highest-priority layer that has it, along with the
+    /// [``Source``] it was read from.
+    pub fn get_field_with_source<T: FromStr>(&self, path: impl AsRef<Path>) -> Result<(T, Source), ConfigError> {
+        let path = path.as_ref();
+
+        for (source, layer) in &self.layers {
+            if let Ok(data) = layer.read(path) {
+                let contents = String::from_utf8(data).map_err(|_| ConfigError::FromStrErr)?;
+                return T::from_str(contents.as_str()).map(|value| (value, *source)).map_err(|_| ConfigError::FromStrErr);
+            }
+        }
+
+        Err(ConfigError::FieldMissing)
+    }
+
+    /// Provides the value of the field from the highest-priority layer that has it.
+    pub fn get_field<T: FromStr>(&self, path: impl AsRef<Path>) -> Result<T, ConfigError> {
+        self.get_field_with_source(path).map(|(value, _)| value)
+    }
+
+    /// Checks if a flag is enabled in any layer.
+    pub fn get_flag(&self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        self.layers.iter().any(|(_, layer)| layer.read(path).is_ok())
+    }
+
+    /// Create a field in the writable layer and assigns it the value provided.
+    pub fn set_field(&mut self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), ConfigError> {
+        self.writable_layer().write(path.as_ref(), contents.as_ref())?;
+        self.flush();
+        Ok(())
+    }
+
+    /// If ``flag`` is set to true, enable the flag in the writable layer if it isn't already set.
+    /// Otherwise, disable the flag in the writable layer.
+    pub fn set_flag(&mut self, path: impl AsRef<Path>, flag: bool) -> Result<(), ConfigError> {
+        if flag {
+            self.set_field(path, [])
+        } else {
+            self.writable_layer().remove(path.as_ref())?;
+            self.flush();
+            Ok(())
+        }
+    }
+
+    /// Lists every key present in more than one layer.
+    pub fn check_conflicts(&self) -> Vec<PathBuf> {
+        let mut occurrences: std::collections::HashMap<PathBuf, usize> = std::collections::HashMap::new();
+
+        for (_, layer) in &self.layers {
+            if let Ok(keys) = layer.list() {
+                for key in keys {
+                    *occurrences.entry(key).or_insert(0) += 1;
+                }
+            }
+        }
+
+        occurrences.into_iter().filter(|(_, count)| *count > 1).map(|(key, _)| key).collect()
+    }
+
+    fn writable_layer(&self) -> &dyn ConfigStorage {
+        self.layers[self.writable].1.as_ref()
+    }
+
+    fn flush(&self) {
+        let layer = self.writable_layer();
+        if layer.require_flushing() {
+            layer.perform_flush();
+        }
+    }
+}
+
 #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
 use serde::{de::DeserializeOwned, Serialize};
 
+/// A whole-struct configuration document, read and written as a single file under
+/// [``NamedConfig::file_name``] instead of one file per field.
+pub trait NamedConfig {
+    /// The name of the file the document is stored under, relative to the storage root.
+    fn file_name() -> &'static str;
+}
+
+/// A serialization format compiled into this crate, selected either explicitly or by file extension.
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    #[cfg(feature = "json")]
+    Json,
+    #[cfg(feature = "toml")]
+    Toml,
+    #[cfg(feature = "yaml")]
+    Yaml,
+}
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+impl FileFormat {
+    /// Determines the format from a path's extension (`.json`, `.toml`, `.yaml`/`.yml`).
+    pub fn from_extension(path: impl AsRef<Path>) -> Option<Self> {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "json")]
+            Some("json") => Some(Self::Json),
+            #[cfg(feature = "toml")]
+            Some("toml") => Some(Self::Toml),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
+impl<CS: ConfigStorage> StorageHolder<CS> {
+    /// Create a field in the configuration and assigns it the value provided, serialized using ``format``.
+    pub fn set_field_as<T: Serialize>(&mut self, format: FileFormat, path: impl AsRef<Path>, field: &T) -> Result<(), ConfigError> {
+        let serialized = match format {
+            #[cfg(feature = "json")]
+            FileFormat::Json => serde_json::to_string(field).unwrap(),
+            #[cfg(feature = "toml")]
+            FileFormat::Toml => serde_toml::ser::to_string_pretty(field).unwrap(),
+            #[cfg(feature = "yaml")]
+            FileFormat::Yaml => serde_yaml::to_string(field).unwrap(),
+        };
+
+        self.write(path, serialized)
+    }
+
+    /// Provides the value of the field if it exists and deserializes it using ``format``.
+    pub fn get_field_as<T: DeserializeOwned>(&self, format: FileFormat, path: impl AsRef<Path>) -> Result<T, ConfigError> {
+        let contents = self.read_to_string(path).map_err(|_| ConfigError::FieldMissing)?;
+
+        Ok(match format {
+            #[cfg(feature = "json")]
+            FileFormat::Json => serde_json::from_str(&contents).unwrap(),
+            #[cfg(feature = "toml")]
+            FileFormat::Toml => serde_toml::de::from_str(&contents).unwrap(),
+            #[cfg(feature = "yaml")]
+            FileFormat::Yaml => serde_yaml::from_str(&contents).unwrap(),
+        })
+    }
+
+    /// Create a field in the configuration, serialized using the format inferred from ``path``'s extension.
+    pub fn set_field_auto<T: Serialize>(&mut self, path: impl AsRef<Path>, field: &T) -> Result<(), ConfigError> {
+        let format = FileFormat::from_extension(&path).ok_or(ConfigError::UnknownFormat)?;
+        self.set_field_as(format, path, field)
+    }
+
+    /// Provides the value of the field if it exists, deserialized using the format inferred from ``path``'s extension.
+    pub fn get_field_auto<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T, ConfigError> {
+        let format = FileFormat::from_extension(&path).ok_or(ConfigError::UnknownFormat)?;
+        self.get_field_as(format, path)
+    }
+}
+
 #[cfg(feature = "json")]
 impl<CS: ConfigStorage> StorageHolder<CS> {
     /// Create a field in the configuration and assigns it the value provided, serialized as a JSON.
     pub fn set_field_json<T: Serialize>(&mut self, path: impl AsRef<Path>, field: &T) -> Result<(), ConfigError> {
-        Ok(self.write(path, serde_json::to_string(field).unwrap())?)
+        self.set_field_as(FileFormat::Json, path, field)
     }
 
     /// Provides the value of the field if it exists and deserializes it from a JSON.
     pub fn get_field_json<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T, ConfigError> {
-        Ok(serde_json::from_str(&self.read_to_string(path).map_err(|_| ConfigError::FieldMissing)?).unwrap())
+        self.get_field_as(FileFormat::Json, path)
+    }
+
+    /// Loads ``T`` as a single document named after [``NamedConfig::file_name``], filling in any
+    /// fields missing from the stored document (or creating it outright) from ``T::default()``.
+    pub fn load_or_create<T: NamedConfig + DeserializeOwned + Serialize + Default>(&mut self) -> T {
+        let path = PathBuf::from(T::file_name());
+        let format = FileFormat::from_extension(&path).unwrap_or(FileFormat::Json);
+        let default = T::default();
+
+        let stored: Option<serde_json::Value> = self.get_field_as(format, &path).ok();
+
+        let merged = match stored {
+            Some(serde_json::Value::Object(mut stored_fields)) => {
+                if let serde_json::Value::Object(default_fields) = serde_json::to_value(&default).unwrap() {
+                    for (key, value) in default_fields {
+                        stored_fields.entry(key).or_insert(value);
+                    }
+                }
+                serde_json::Value::Object(stored_fields)
+            },
+            _ => serde_json::to_value(&default).unwrap(),
+        };
+
+        let config: T = serde_json::from_value(merged).unwrap_or_else(|_| T::default());
+
+        self.set_field_as(format, &path, &config).unwrap();
+
+        config
     }
 }
 
@@ -298,12 +715,12 @@ impl<CS: ConfigStorage> StorageHolder<CS> {
 impl<CS: ConfigStorage> StorageHolder<CS> {
     /// Create a field in the configuration and assigns it the value provided, serialized as a TOML.
     pub fn set_field_toml<T: Serialize>(&mut self, path: impl AsRef<Path>, field: &T) -> Result<(), ConfigError> {
-        Ok(self.write(path, serde_toml::ser::to_string_pretty(field).unwrap())?)
+        self.set_field_as(FileFormat::Toml, path, field)
     }
 
     /// Provides the value of the field if it exists and deserializes it from a TOML.
     pub fn get_field_toml<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T, ConfigError> {
-        Ok(serde_toml::de::from_str(&self.read_to_string(path).map_err(|_| ConfigError::FieldMissing)?).unwrap())
+        self.get_field_as(FileFormat::Toml, path)
     }
 }
 
@@ -311,12 +728,12 @@ impl<CS: ConfigStorage> StorageHolder<CS> {
 impl<CS: ConfigStorage> StorageHolder<CS> {
     /// Create a field in the configuration and assigns it the value provided, serialized as a YAML.
     pub fn set_field_yaml<T: Serialize>(&mut self, path: impl AsRef<Path>, field: &T) -> Result<(), ConfigError> {
-        Ok(self.write(path, serde_yaml::to_string(field).unwrap())?)
+        self.set_field_as(FileFormat::Yaml, path, field)
     }
 
     /// Provides the value of the field if it exists and deserializes it from a YAML.
     pub fn get_field_yaml<T: DeserializeOwned>(&self, path: impl AsRef<Path>) -> Result<T, ConfigError> {
-        Ok(serde_yaml::from_str(&self.read_to_string(path).map_err(|_| ConfigError::FieldMissing)?).unwrap())
+        self.get_field_as(FileFormat::Yaml, path)
     }
 }
 
@@ -336,3 +753,128 @@ impl<CS: ConfigStorage> StorageHolder<CS> {
 
 //     Ok(DebugSavedataStorage(path).into())
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_set_field_round_trip() {
+        let mut holder = StorageHolder::new(MemoryStorage::new());
+
+        holder.set_field("field", "hello").unwrap();
+
+        assert_eq!(holder.get_field::<String>("field").unwrap(), "hello");
+    }
+
+    #[test]
+    fn get_field_missing_errors() {
+        let holder = StorageHolder::new(MemoryStorage::new());
+
+        assert!(matches!(holder.get_field::<String>("missing"), Err(ConfigError::FieldMissing)));
+    }
+
+    #[test]
+    fn get_set_flag_round_trip() {
+        let mut holder = StorageHolder::new(MemoryStorage::new());
+
+        assert!(!holder.get_flag("flag"));
+
+        holder.set_flag("flag", true).unwrap();
+        assert!(holder.get_flag("flag"));
+
+        holder.set_flag("flag", false).unwrap();
+        assert!(!holder.get_flag("flag"));
+    }
+
+    #[test]
+    fn with_transaction_defers_flush_until_body_returns() {
+        let mut holder = StorageHolder::new(MemoryStorage::new());
+
+        holder.with_transaction(|holder| {
+            holder.set_field("a", "1").unwrap();
+            holder.set_field("b", "2").unwrap();
+            assert!(holder.1);
+        });
+
+        assert!(!holder.1);
+        assert_eq!(holder.get_field::<String>("a").unwrap(), "1");
+        assert_eq!(holder.get_field::<String>("b").unwrap(), "2");
+    }
+
+    #[test]
+    fn with_transaction_restores_flag_when_body_panics() {
+        let mut holder = StorageHolder::new(MemoryStorage::new());
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            holder.with_transaction(|holder| {
+                holder.set_field("a", "1").unwrap();
+                panic!("boom");
+            });
+        }));
+
+        assert!(result.is_err());
+        assert!(!holder.1);
+    }
+
+    #[test]
+    fn layered_storage_prefers_higher_priority_layer() {
+        let sd_card = MemoryStorage::new();
+        sd_card.write(Path::new("field"), b"sd").unwrap();
+
+        let save_data = MemoryStorage::new();
+        save_data.write(Path::new("field"), b"save").unwrap();
+        save_data.write(Path::new("only_in_save_data"), b"save").unwrap();
+
+        let storage = LayeredStorage::new(vec![(Source::SdCard, Box::new(sd_card)), (Source::SaveData, Box::new(save_data))], 0);
+
+        let (value, source): (String, Source) = storage.get_field_with_source("field").unwrap();
+        assert_eq!(value, "sd");
+        assert_eq!(source, Source::SdCard);
+
+        let (value, source): (String, Source) = storage.get_field_with_source("only_in_save_data").unwrap();
+        assert_eq!(value, "save");
+        assert_eq!(source, Source::SaveData);
+
+        assert_eq!(storage.check_conflicts(), vec![PathBuf::from("field")]);
+    }
+
+    #[cfg(feature = "json")]
+    #[derive(serde::Serialize, serde::Deserialize, Default, PartialEq, Debug)]
+    struct TestConfig {
+        kept: u32,
+        added_later: u32,
+    }
+
+    #[cfg(feature = "json")]
+    impl NamedConfig for TestConfig {
+        fn file_name() -> &'static str {
+            "test_config.json"
+        }
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_or_create_writes_default_when_missing() {
+        let mut holder = StorageHolder::new(MemoryStorage::new());
+
+        let config: TestConfig = holder.load_or_create();
+
+        assert_eq!(config, TestConfig::default());
+        assert_eq!(holder.get_field_json::<TestConfig>("test_config.json").unwrap(), TestConfig::default());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn load_or_create_fills_in_fields_missing_from_an_older_document() {
+        let mut holder = StorageHolder::new(MemoryStorage::new());
+
+        // Simulate a document written by an older version of the plugin, before `added_later` existed.
+        holder.set_field("test_config.json", r#"{"kept": 7}"#).unwrap();
+
+        let config: TestConfig = holder.load_or_create();
+
+        assert_eq!(config, TestConfig { kept: 7, added_later: 0 });
+        assert_eq!(holder.get_field_json::<TestConfig>("test_config.json").unwrap(), config);
+    }
+}